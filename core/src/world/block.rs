@@ -0,0 +1,25 @@
+/// The type of a block.
+///
+/// Some block types carry additional structured data beyond this
+/// enum — see [`crate::world::BlockEntity`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Block {
+    Air,
+    Stone,
+    Dirt,
+    Sign,
+    Chest,
+}
+
+impl Block {
+    /// Whether this block type can hold block-entity data.
+    pub fn can_have_block_entity(self) -> bool {
+        matches!(self, Block::Sign | Block::Chest)
+    }
+}
+
+impl Default for Block {
+    fn default() -> Self {
+        Block::Air
+    }
+}
@@ -0,0 +1,14 @@
+/// Structured data attached to a block that needs more state than the
+/// `Block` enum alone can hold, e.g. a sign's text. Only signs have a
+/// data-carrying variant; other blocks use `Empty`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum BlockEntity {
+    Sign { lines: [String; 4] },
+    Empty,
+}
+
+impl Default for BlockEntity {
+    fn default() -> Self {
+        BlockEntity::Empty
+    }
+}
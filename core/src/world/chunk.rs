@@ -0,0 +1,67 @@
+use hashbrown::HashMap;
+
+use crate::world::{block::Block, block_entity::BlockEntity, BlockPosition, ChunkPosition};
+
+const WIDTH: usize = 16;
+const HEIGHT: usize = 256;
+
+/// A 16x256x16 column of blocks.
+///
+/// Block entity data (see [`BlockEntity`]) is stored separately from
+/// the dense block array, keyed by chunk-relative position, since
+/// only a small minority of blocks (signs, chests, spawners) carry
+/// any.
+pub struct Chunk {
+    position: ChunkPosition,
+    blocks: Vec<Block>,
+    block_entities: HashMap<BlockPosition, BlockEntity>,
+}
+
+impl Chunk {
+    pub fn new(position: ChunkPosition) -> Self {
+        Self {
+            position,
+            blocks: vec![Block::Air; WIDTH * HEIGHT * WIDTH],
+            block_entities: HashMap::new(),
+        }
+    }
+
+    pub fn position(&self) -> ChunkPosition {
+        self.position
+    }
+
+    /// Gets the block at the given chunk-relative coordinates.
+    pub fn block_at(&self, x: usize, y: usize, z: usize) -> Block {
+        self.blocks[block_index(x, y, z)]
+    }
+
+    /// Sets the block at the given chunk-relative coordinates.
+    ///
+    /// Clears any stale block entity at that position whenever the
+    /// block type actually changes (even between two block-entity-
+    /// capable types, e.g. a `Sign` replaced by a `Chest`), so the two
+    /// stores never desync.
+    pub fn set_block_at(&mut self, x: usize, y: usize, z: usize, block: Block) {
+        let idx = block_index(x, y, z);
+        let changed = self.blocks[idx] != block;
+        self.blocks[idx] = block;
+        if changed {
+            self.block_entities
+                .remove(&BlockPosition::new(x as i32, y as i32, z as i32));
+        }
+    }
+
+    /// Gets the block entity at the given chunk-relative position.
+    pub fn block_entity_at(&self, pos: BlockPosition) -> Option<&BlockEntity> {
+        self.block_entities.get(&pos)
+    }
+
+    /// Sets the block entity at the given chunk-relative position.
+    pub fn set_block_entity(&mut self, pos: BlockPosition, data: BlockEntity) {
+        self.block_entities.insert(pos, data);
+    }
+}
+
+fn block_index(x: usize, y: usize, z: usize) -> usize {
+    (y * WIDTH * WIDTH) + (z * WIDTH) + x
+}
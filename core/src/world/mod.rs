@@ -1,4 +1,5 @@
 use crate::world::block::*;
+use crate::world::block_entity::BlockEntity;
 use crate::world::chunk::Chunk;
 use glm::{DVec3, Vec3};
 use hashbrown::HashMap;
@@ -10,6 +11,7 @@ use std::ops::{Add, Sub};
 use std::sync::Arc;
 
 pub mod block;
+pub mod block_entity;
 #[allow(clippy::cast_lossless)]
 pub mod chunk;
 
@@ -298,6 +300,26 @@ impl ChunkMap {
             .is_ok()
     }
 
+    /// Retrieves the block entity at the given position, or `None`
+    /// if its chunk is not loaded or it has no block entity.
+    pub fn block_entity_at(&self, pos: BlockPosition) -> Option<BlockEntity> {
+        let (x, y, z) = chunk_relative_pos(pos);
+        self.chunk_at(pos.chunk_pos())
+            .and_then(|chunk| chunk.block_entity_at(relative_block_pos(x, y, z)).cloned())
+    }
+
+    /// Sets the block entity at the given position.
+    ///
+    /// Returns `true` if it was set, or `false` if its chunk was not
+    /// loaded and thus no operation was performed.
+    pub fn set_block_entity(&self, pos: BlockPosition, data: BlockEntity) -> bool {
+        let (x, y, z) = chunk_relative_pos(pos);
+
+        self.chunk_at_mut(pos.chunk_pos())
+            .map(|mut chunk| chunk.set_block_entity(relative_block_pos(x, y, z), data))
+            .is_some()
+    }
+
     /// Returns an iterator over chunks.
     pub fn iter_chunks(&self) -> impl IntoIterator<Item = &Arc<RwLock<Chunk>>> {
         self.0.iter()
@@ -334,6 +356,12 @@ fn chunk_relative_pos(block_pos: BlockPosition) -> (usize, usize, usize) {
     )
 }
 
+/// Builds the chunk-relative `BlockPosition` key used to index block
+/// entities within a single `Chunk`.
+fn relative_block_pos(x: usize, y: usize, z: usize) -> BlockPosition {
+    BlockPosition::new(x as i32, y as i32, z as i32)
+}
+
 pub trait ChunkGenerator {
     fn generate(&self, chunk: &mut Chunk);
 }
@@ -0,0 +1,47 @@
+use crate::{component::Component, ecs::Ecs, entity::EntityId};
+
+/// A tuple of components that can be added to an entity all at once.
+///
+/// Implemented for tuples of up to eight [`Component`]s; used by
+/// [`Ecs::spawn_bundle`] and the batch-spawning APIs.
+pub trait ComponentBundle {
+    #[doc(hidden)]
+    fn add_to_entity(self, ecs: &mut Ecs, entity: EntityId);
+
+    /// Reserves capacity for `additional` more entities in every
+    /// component storage this bundle touches, so that spawning a
+    /// large batch of bundles grows each storage once up front
+    /// rather than once per entity.
+    #[doc(hidden)]
+    fn reserve(ecs: &mut Ecs, additional: usize);
+}
+
+macro_rules! impl_component_bundle {
+    ($($name:ident),+) => {
+        impl<$($name: Component),+> ComponentBundle for ($($name,)+) {
+            #[allow(non_snake_case)]
+            fn add_to_entity(self, ecs: &mut Ecs, entity: EntityId) {
+                let ($($name,)+) = self;
+                $(
+                    ecs.insert(entity, $name)
+                        .expect("entity was just spawned and must be alive");
+                )+
+            }
+
+            fn reserve(ecs: &mut Ecs, additional: usize) {
+                $(
+                    ecs.reserve_for::<$name>(additional);
+                )+
+            }
+        }
+    };
+}
+
+impl_component_bundle!(A);
+impl_component_bundle!(A, B);
+impl_component_bundle!(A, B, C);
+impl_component_bundle!(A, B, C, D);
+impl_component_bundle!(A, B, C, D, E);
+impl_component_bundle!(A, B, C, D, E, F);
+impl_component_bundle!(A, B, C, D, E, F, G);
+impl_component_bundle!(A, B, C, D, E, F, G, H);
@@ -0,0 +1,107 @@
+use std::marker::PhantomData;
+
+use crate::{component::Component, ecs::Ecs, query::QueryFilter};
+
+/// Compares two ticks produced by [`Ecs::increment_tick`], tolerating
+/// wraparound of the underlying `u64` counter.
+///
+/// `tick` counts as after `last_run` if it lies within the "recent"
+/// half of the tick space relative to `last_run`; this is the usual
+/// trick for making a wrapping counter's ordering well-defined near
+/// the wraparound point.
+pub(crate) fn tick_is_newer(tick: u64, last_run: u64) -> bool {
+    let delta = tick.wrapping_sub(last_run);
+    delta != 0 && delta < u64::MAX / 2
+}
+
+/// A [`QueryFilter`] that matches entities whose `T` component was
+/// inserted since `last_run`.
+///
+/// A component that was just inserted is also considered `Changed`,
+/// since insertion stamps both ticks together.
+pub struct Added<T: Component> {
+    last_run: u64,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T: Component> Added<T> {
+    /// Builds a filter matching components added since `last_run`,
+    /// a tick previously captured by the caller (e.g. at the end of
+    /// its last run through this query).
+    pub fn since(last_run: u64) -> Self {
+        Self {
+            last_run,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, T: Component> QueryFilter<'a> for Added<T> {
+    fn matches(&self, ecs: &'a Ecs, index: u32) -> bool {
+        let Some(storage) = ecs.storage_for_type::<T>() else {
+            return false;
+        };
+        let Some(dense) = storage.dense_index_of(index) else {
+            return false;
+        };
+        tick_is_newer(storage.added_tick(dense), self.last_run)
+    }
+}
+
+/// A [`QueryFilter`] that matches entities whose `T` component was
+/// mutated (via [`Ecs::get_mut`] or re-`insert`ed) since `last_run`.
+pub struct Changed<T: Component> {
+    last_run: u64,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T: Component> Changed<T> {
+    /// Builds a filter matching components changed since `last_run`,
+    /// a tick previously captured by the caller.
+    pub fn since(last_run: u64) -> Self {
+        Self {
+            last_run,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, T: Component> QueryFilter<'a> for Changed<T> {
+    fn matches(&self, ecs: &'a Ecs, index: u32) -> bool {
+        let Some(storage) = ecs.storage_for_type::<T>() else {
+            return false;
+        };
+        let Some(dense) = storage.dense_index_of(index) else {
+            return false;
+        };
+        tick_is_newer(storage.changed_tick(dense), self.last_run)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::impl_component;
+
+    struct Velocity(f32);
+    impl_component!(Velocity);
+
+    #[test]
+    fn changed_fires_for_mutations_made_through_a_query() {
+        let mut ecs = Ecs::new();
+        let entity = ecs.spawn_bundle((Velocity(1.0),));
+        ecs.increment_tick();
+        let last_run = ecs.current_tick();
+        ecs.increment_tick();
+
+        for (_, velocity) in ecs.query::<(&mut Velocity,)>() {
+            velocity.0 += 1.0;
+        }
+
+        let changed: Vec<_> = ecs
+            .query_filtered::<(&Velocity,), _>(Changed::<Velocity>::since(last_run))
+            .map(|(entity, _)| entity)
+            .collect();
+        assert_eq!(changed, vec![entity]);
+    }
+}
@@ -0,0 +1,91 @@
+use std::alloc::Layout;
+use std::any::{type_name, TypeId};
+
+/// Which storage backend holds a component type's data.
+///
+/// See `SparseSetStorage` and `TableStorage` in the `storage` module.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageKind {
+    /// Good default: O(1) random insert/remove via a sparse index
+    /// alongside the packed component array.
+    SparseSet,
+    /// A plain dense array with no sparse index, kept sorted by
+    /// entity index. O(n) insert/remove; use for a few hot, densely
+    /// populated components that are iterated every tick.
+    Table,
+}
+
+/// Marker trait for types that can be stored as components in an
+/// `Ecs`. Implement via [`crate::impl_component!`] rather than by hand.
+pub trait Component: Send + Sync + 'static {
+    /// Which storage backs this component type. Override via
+    /// [`crate::impl_component!`]'s second form for hot components
+    /// that should live in a [`StorageKind::Table`].
+    const STORAGE: StorageKind = StorageKind::SparseSet;
+}
+
+/// Implements [`Component`] for `$ty`, optionally picking its
+/// [`StorageKind`] (defaults to `StorageKind::SparseSet`).
+///
+/// ```ignore
+/// impl_component!(Health);
+/// impl_component!(Position, StorageKind::Table);
+/// ```
+#[macro_export]
+macro_rules! impl_component {
+    ($ty:ty) => {
+        impl $crate::Component for $ty {}
+    };
+    ($ty:ty, $storage:expr) => {
+        impl $crate::Component for $ty {
+            const STORAGE: $crate::StorageKind = $storage;
+        }
+    };
+}
+
+/// Uniquely identifies a component type, independent of its Rust type
+/// parameter. Used as the key into the `Ecs`'s per-type storage map.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ComponentTypeId(TypeId);
+
+impl ComponentTypeId {
+    pub fn of<T: Component>() -> Self {
+        Self(TypeId::of::<T>())
+    }
+}
+
+/// Type-erased information needed to store and drop a component
+/// without knowing its Rust type statically.
+///
+/// Produced via [`ComponentMeta::of`] and threaded through the
+/// untyped insertion paths (`EntityBuilder`, `spawn_builder`).
+#[derive(Clone, Copy)]
+pub struct ComponentMeta {
+    pub type_id: ComponentTypeId,
+    pub(crate) name: &'static str,
+    pub(crate) layout: Layout,
+    pub(crate) drop_in_place: unsafe fn(*mut u8),
+    pub(crate) storage_kind: StorageKind,
+}
+
+impl ComponentMeta {
+    pub fn of<T: Component>() -> Self {
+        Self {
+            type_id: ComponentTypeId::of::<T>(),
+            name: type_name::<T>(),
+            layout: Layout::new::<T>(),
+            drop_in_place: drop_in_place::<T>,
+            storage_kind: T::STORAGE,
+        }
+    }
+}
+
+impl std::fmt::Debug for ComponentMeta {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ComponentMeta").field("name", &self.name).finish()
+    }
+}
+
+unsafe fn drop_in_place<T>(ptr: *mut u8) {
+    std::ptr::drop_in_place(ptr as *mut T);
+}
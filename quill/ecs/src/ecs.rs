@@ -7,7 +7,10 @@ use crate::{
     component::{Component, ComponentMeta, ComponentTypeId},
     entity::{Entities, EntityId},
     entity_builder::EntityBuilder,
-    storage::SparseSetStorage,
+    mask::ComponentMasks,
+    query::{Query, QueryFilter, QueryIter},
+    relation::{Relation, RelationStore},
+    storage::ComponentStorage,
 };
 
 #[derive(Debug, thiserror::Error)]
@@ -22,6 +25,10 @@ pub enum ComponentError {
 #[error("entity is dead or was unloaded")]
 pub struct EntityDead;
 
+#[derive(Debug, thiserror::Error)]
+#[error("entity index is occupied by a live entity of a newer generation")]
+pub struct GenerationConflict;
+
 /// The entity-component data structure.
 ///
 /// An `Ecs` stores _components_ for _entities_.
@@ -32,8 +39,11 @@ pub struct EntityDead;
 /// Feather, the `World` stores blocks, not entities.)
 #[derive(Default)]
 pub struct Ecs {
-    components: AHashMap<ComponentTypeId, SparseSetStorage>,
+    components: AHashMap<ComponentTypeId, ComponentStorage>,
     entities: Entities,
+    masks: ComponentMasks,
+    relations: RelationStore,
+    tick: u64,
 }
 
 impl Ecs {
@@ -53,6 +63,22 @@ impl Ecs {
             .ok_or_else(|| ComponentError::MissingComponent(type_name::<T>()))
     }
 
+    /// Mutably gets a component for an entity, marking it as changed
+    /// as of the current tick (see [`Self::increment_tick`]) so that
+    /// `Changed<T>` query filters pick it up.
+    ///
+    /// Time complexity: O(1) for a `StorageKind::SparseSet` component;
+    /// O(log n) for a `StorageKind::Table` one (binary search).
+    pub fn get_mut<T: Component>(&mut self, entity: EntityId) -> Result<&mut T, ComponentError> {
+        self.check_entity(entity)?;
+        let tick = self.tick;
+        let storage = self.storage_mut_for::<T>()?;
+        storage.mark_changed(entity.index(), tick);
+        storage
+            .get_mut::<T>(entity.index())
+            .ok_or_else(|| ComponentError::MissingComponent(type_name::<T>()))
+    }
+
     /// Inserts a component for an entity.
     ///
     /// If the entity already has this component, then it
@@ -65,11 +91,28 @@ impl Ecs {
         component: T,
     ) -> Result<(), EntityDead> {
         self.check_entity(entity)?;
+        let tick = self.tick;
         let storage = self.storage_or_insert_for::<T>();
-        storage.insert(entity.index(), component);
+        storage.insert(entity.index(), component, tick);
+        self.masks.insert(entity.index(), ComponentTypeId::of::<T>());
         Ok(())
     }
 
+    /// Advances the ECS's tick counter.
+    ///
+    /// Called once per game loop; `Added<T>`/`Changed<T>` query
+    /// filters compare component ticks against a "last run" tick the
+    /// caller captured earlier via [`Self::current_tick`].
+    pub fn increment_tick(&mut self) {
+        self.tick = self.tick.wrapping_add(1);
+    }
+
+    /// The current tick, for callers that want to capture a "last
+    /// run" tick to later pass to `Added`/`Changed`.
+    pub fn current_tick(&self) -> u64 {
+        self.tick
+    }
+
     /// Removes a component from an entity.
     ///
     /// Returns `Err` if the entity does not exist
@@ -78,6 +121,7 @@ impl Ecs {
         self.check_entity(entity)?;
         let storage = self.storage_mut_for::<T>()?;
         if storage.remove(entity.index()) {
+            self.masks.remove(entity.index(), ComponentTypeId::of::<T>());
             Ok(())
         } else {
             Err(ComponentError::MissingComponent(type_name::<T>()))
@@ -99,12 +143,14 @@ impl Ecs {
     /// Time complexity: O(n) with respect to the number of components in `builder`.
     pub fn spawn_builder(&mut self, builder: &mut EntityBuilder) -> EntityId {
         let entity = self.spawn_empty();
+        let tick = self.tick;
 
         for (component_meta, component) in builder.drain() {
             let storage = self.storage_or_insert_for_untyped(component_meta);
             unsafe {
-                storage.insert_raw(entity.index(), component.as_ptr());
+                storage.insert_raw(entity.index(), component.as_ptr(), tick);
             }
+            self.masks.insert(entity.index(), component_meta.type_id);
         }
 
         builder.reset();
@@ -124,19 +170,171 @@ impl Ecs {
         entity
     }
 
+    /// Spawns many entities at once from an iterator of bundles.
+    ///
+    /// Reserves capacity in every touched component storage for the
+    /// whole batch up front (rather than growing storages as each
+    /// bundle is inserted), which is far faster than calling
+    /// `spawn_bundle` in a loop when spawning e.g. a burst of
+    /// item-drop or particle entities.
+    pub fn spawn_batch<I, B>(&mut self, bundles: I) -> Vec<EntityId>
+    where
+        I: IntoIterator<Item = B>,
+        B: ComponentBundle,
+    {
+        let bundles: Vec<B> = bundles.into_iter().collect();
+        B::reserve(self, bundles.len());
+
+        let entities: Vec<EntityId> = bundles.iter().map(|_| self.spawn_empty()).collect();
+        for (entity, bundle) in entities.iter().copied().zip(bundles) {
+            bundle.add_to_entity(self, entity);
+        }
+        entities
+    }
+
+    /// Adds `bundle` to `entity`, reinstating `entity` at its exact
+    /// index and generation first if it isn't already live.
+    ///
+    /// Useful for deterministic replay and network-synced entity ids,
+    /// where the caller has an `EntityId` from a previous `Ecs` and
+    /// wants it to refer to the same entity here.
+    ///
+    /// If that index is currently occupied by a live entity of an
+    /// older generation, that entity is despawned first (its
+    /// components, mask, and relations are cleaned up exactly as
+    /// [`Self::despawn`] would), so it isn't silently left behind
+    /// under the new generation. Returns `Err` instead if it's
+    /// occupied by a live entity of a **newer** generation, rather
+    /// than rewinding it.
+    pub fn insert_or_spawn<B: ComponentBundle>(
+        &mut self,
+        entity: EntityId,
+        bundle: B,
+    ) -> Result<(), GenerationConflict> {
+        if self.check_entity(entity).is_err() {
+            if let Some(occupant) = self.entities.live_id_at(entity.index()) {
+                if occupant.generation() < entity.generation() {
+                    let _ = self.despawn(occupant);
+                }
+            }
+            self.entities
+                .reinstate(entity)
+                .map_err(|_| GenerationConflict)?;
+        }
+        bundle.add_to_entity(self, entity);
+        Ok(())
+    }
+
+    pub(crate) fn reserve_for<T: Component>(&mut self, additional: usize) {
+        self.storage_or_insert_for::<T>()
+            .reserve_additional(additional);
+    }
+
+    /// Iterates all entities holding every component in the query
+    /// tuple `Q`, e.g. `ecs.query::<(&Position, &mut Velocity)>()`.
+    ///
+    /// Yields `(EntityId, Q::Item)` pairs. Iteration is driven by
+    /// whichever requested component currently has the fewest
+    /// entries, probing the other storages to confirm membership, so
+    /// cost is roughly proportional to the smallest involved storage
+    /// rather than the whole `Ecs`.
+    pub fn query<'a, Q: Query<'a>>(&'a self) -> QueryIter<'a, Q> {
+        QueryIter::new(self)
+    }
+
+    /// Like [`Self::query`], but only yields entities also matching
+    /// `filter`, e.g. `ecs.query_filtered::<(&Position,), _>(Changed::<Velocity>::since(last_run))`.
+    pub fn query_filtered<'a, Q: Query<'a>, F: QueryFilter<'a>>(
+        &'a self,
+        filter: F,
+    ) -> QueryIter<'a, Q, F> {
+        QueryIter::with_filter(self, filter)
+    }
+
+    /// Adds a relation of kind `R` from `source` to `target`, e.g.
+    /// `ecs.add_relation(child, parent, ChildOf)`.
+    ///
+    /// A source entity may hold many instances of the same relation
+    /// kind at once, each pointing at a different target.
+    pub fn add_relation<R: Relation>(
+        &mut self,
+        source: EntityId,
+        target: EntityId,
+        data: R,
+    ) -> Result<(), EntityDead> {
+        self.check_entity(source)?;
+        self.check_entity(target)?;
+        self.relations.add(source, target, data);
+        Ok(())
+    }
+
+    /// Iterates every `R` relation `source` holds, yielded as
+    /// `(target, &R)` pairs.
+    pub fn relations<R: Relation>(&self, source: EntityId) -> impl Iterator<Item = (EntityId, &R)> {
+        self.relations.iter(source)
+    }
+
+    /// Removes the `R` relation from `source` to `target`.
+    ///
+    /// Returns `Err` if `source` held no such relation to `target`.
+    pub fn remove_relation<R: Relation>(
+        &mut self,
+        source: EntityId,
+        target: EntityId,
+    ) -> Result<(), ComponentError> {
+        self.check_entity(source)?;
+        if self.relations.remove::<R>(source, target) {
+            Ok(())
+        } else {
+            Err(ComponentError::MissingComponent(type_name::<R>()))
+        }
+    }
+
+    pub(crate) fn storage_for_type<T: Component>(&self) -> Option<&ComponentStorage> {
+        self.components.get(&ComponentTypeId::of::<T>())
+    }
+
+    pub(crate) fn entity_id_at(&self, index: u32) -> EntityId {
+        self.entities.id_at(index)
+    }
+
+    /// Iterates the types of every component `entity` currently
+    /// holds. Mainly useful for introspection, e.g. serializing an
+    /// entity generically. Yields nothing for a dead or unknown
+    /// entity.
+    pub fn entity_components(&self, entity: EntityId) -> impl Iterator<Item = ComponentTypeId> + '_ {
+        let mask: &[ComponentTypeId] = if self.check_entity(entity).is_ok() {
+            self.masks.get(entity.index())
+        } else {
+            &[]
+        };
+        mask.iter().copied()
+    }
+
     /// Despawns an entity. Future access to the entity
     /// will result in `EntityDead`.
     ///
-    /// Time complexity: O(n) with respect to the total number of components
+    /// Also removes every relation pair `entity` holds as a source,
+    /// and every relation pair anywhere in the `Ecs` that targets
+    /// `entity`, so no relation is left dangling.
+    ///
+    /// Time complexity: O(n) with respect to the number of components
+    /// `entity` actually holds, not the total number of components
     /// stored in this ECS.
+    /// Removing a `StorageKind::Table` component additionally shifts
+    /// that storage's later entries, O(len) of that storage.
     pub fn despawn(&mut self, entity: EntityId) -> Result<(), EntityDead> {
         self.entities.deallocate(entity).map_err(|_| EntityDead)?;
 
-        // PERF: could we somehow optimize this linear search
-        // by only checking storages containing the entity?
-        for storage in self.components.values_mut() {
-            storage.remove(entity.index());
+        for &type_id in self.masks.get(entity.index()) {
+            if let Some(storage) = self.components.get_mut(&type_id) {
+                storage.remove(entity.index());
+            }
         }
+        self.masks.clear(entity.index());
+
+        self.relations.remove_all_from(entity);
+        self.relations.remove_all_targeting(entity);
 
         Ok(())
     }
@@ -147,30 +345,81 @@ impl Ecs {
             .map_err(|_| EntityDead)
     }
 
-    fn storage_for<T: Component>(&self) -> Result<&SparseSetStorage, ComponentError> {
+    fn storage_for<T: Component>(&self) -> Result<&ComponentStorage, ComponentError> {
         self.components
             .get(&ComponentTypeId::of::<T>())
             .ok_or_else(|| ComponentError::MissingComponent(type_name::<T>()))
     }
 
-    fn storage_mut_for<T: Component>(&mut self) -> Result<&mut SparseSetStorage, ComponentError> {
+    fn storage_mut_for<T: Component>(&mut self) -> Result<&mut ComponentStorage, ComponentError> {
         self.components
             .get_mut(&ComponentTypeId::of::<T>())
             .ok_or_else(|| ComponentError::MissingComponent(type_name::<T>()))
     }
 
-    fn storage_or_insert_for<T: Component>(&mut self) -> &mut SparseSetStorage {
+    fn storage_or_insert_for<T: Component>(&mut self) -> &mut ComponentStorage {
         self.components
             .entry(ComponentTypeId::of::<T>())
-            .or_insert_with(|| SparseSetStorage::new(ComponentMeta::of::<T>()))
+            .or_insert_with(|| ComponentStorage::new(ComponentMeta::of::<T>()))
     }
 
     fn storage_or_insert_for_untyped(
         &mut self,
         component_meta: ComponentMeta,
-    ) -> &mut SparseSetStorage {
+    ) -> &mut ComponentStorage {
         self.components
             .entry(component_meta.type_id)
-            .or_insert_with(|| SparseSetStorage::new(component_meta))
+            .or_insert_with(|| ComponentStorage::new(component_meta))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::impl_component;
+
+    struct Position(f32);
+    impl_component!(Position);
+
+    struct Velocity(f32);
+    impl_component!(Velocity);
+
+    #[test]
+    fn insert_or_spawn_despawns_a_live_older_generation_occupant() {
+        let mut ecs = Ecs::new();
+        let old = ecs.spawn_bundle((Position(1.0),));
+        // A "future" id at the same index, one generation ahead of
+        // `old`'s live occupant.
+        let newer = EntityId::new(old.index(), old.generation() + 1);
+
+        ecs.insert_or_spawn(newer, (Velocity(2.0),)).unwrap();
+
+        assert!(ecs.get::<Position>(newer).is_err());
+        assert_eq!(
+            ecs.entity_components(newer).collect::<Vec<_>>(),
+            vec![ComponentTypeId::of::<Velocity>()]
+        );
+    }
+
+    #[test]
+    fn insert_or_spawn_rejects_a_live_newer_generation() {
+        let mut ecs = Ecs::new();
+        let stale = ecs.spawn_bundle((Position(1.0),));
+        ecs.despawn(stale).unwrap();
+        let current = ecs.spawn_bundle((Position(2.0),));
+        assert!(current.generation() > stale.generation());
+
+        assert!(ecs.insert_or_spawn(stale, (Velocity(3.0),)).is_err());
+        assert!(ecs.get::<Position>(current).is_ok());
+    }
+
+    #[test]
+    fn entity_components_is_empty_for_a_stale_recycled_index() {
+        let mut ecs = Ecs::new();
+        let stale = ecs.spawn_bundle((Position(1.0),));
+        ecs.despawn(stale).unwrap();
+        ecs.spawn_bundle((Velocity(2.0),));
+
+        assert_eq!(ecs.entity_components(stale).collect::<Vec<_>>(), vec![]);
     }
 }
\ No newline at end of file
@@ -0,0 +1,170 @@
+/// Uniquely identifies an entity stored in an `Ecs`.
+///
+/// An `EntityId` pairs a dense `index` (used to index into every
+/// component storage) with a `generation` counter. When an entity
+/// is despawned, its index is recycled but its generation is bumped,
+/// so a stale `EntityId` held elsewhere can be detected rather than
+/// silently aliasing whatever entity now occupies that index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EntityId {
+    index: u32,
+    generation: u32,
+}
+
+impl EntityId {
+    pub(crate) fn new(index: u32, generation: u32) -> Self {
+        Self { index, generation }
+    }
+
+    /// The dense index used to look up this entity's components.
+    pub fn index(self) -> u32 {
+        self.index
+    }
+
+    /// The generation this entity was allocated at.
+    pub fn generation(self) -> u32 {
+        self.generation
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("entity generation mismatch (entity was despawned)")]
+pub struct GenerationMismatch;
+
+#[derive(Default)]
+struct Slot {
+    generation: u32,
+    alive: bool,
+}
+
+/// Allocates `EntityId`s and tracks their liveness and generation.
+///
+/// `Entities` knows nothing about components; it is purely the
+/// id-allocation and liveness half of the `Ecs`.
+#[derive(Default)]
+pub struct Entities {
+    slots: Vec<Slot>,
+    free: Vec<u32>,
+}
+
+impl Entities {
+    /// Allocates a new, live entity id.
+    ///
+    /// Time complexity: O(1)
+    pub fn allocate(&mut self) -> EntityId {
+        if let Some(index) = self.free.pop() {
+            let slot = &mut self.slots[index as usize];
+            slot.alive = true;
+            EntityId::new(index, slot.generation)
+        } else {
+            let index = self.slots.len() as u32;
+            self.slots.push(Slot {
+                generation: 0,
+                alive: true,
+            });
+            EntityId::new(index, 0)
+        }
+    }
+
+    /// Marks an entity as dead, freeing its index for reuse under
+    /// a bumped generation.
+    pub fn deallocate(&mut self, entity: EntityId) -> Result<(), GenerationMismatch> {
+        self.check_generation(entity)?;
+        let slot = &mut self.slots[entity.index() as usize];
+        slot.alive = false;
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free.push(entity.index());
+        Ok(())
+    }
+
+    /// Reinstates `entity` as live at its exact index and generation.
+    ///
+    /// Used by [`crate::Ecs::insert_or_spawn`], which first despawns
+    /// any live entity already occupying this index under an older
+    /// generation, so this method itself simply refuses to run over
+    /// any live slot that doesn't already match `entity`'s generation
+    /// exactly — it never advances or clobbers a live occupant.
+    pub(crate) fn reinstate(&mut self, entity: EntityId) -> Result<(), GenerationMismatch> {
+        let index = entity.index() as usize;
+        while self.slots.len() <= index {
+            self.slots.push(Slot::default());
+        }
+
+        let slot = &mut self.slots[index];
+        if slot.alive && slot.generation != entity.generation() {
+            return Err(GenerationMismatch);
+        }
+
+        if !slot.alive {
+            self.free.retain(|&free_index| free_index != entity.index());
+        }
+        slot.alive = true;
+        slot.generation = entity.generation();
+        Ok(())
+    }
+
+    /// Like [`Self::id_at`], but returns `None` instead of panicking
+    /// if `index` does not refer to a live entity.
+    pub(crate) fn live_id_at(&self, index: u32) -> Option<EntityId> {
+        let slot = self.slots.get(index as usize)?;
+        slot.alive.then(|| EntityId::new(index, slot.generation))
+    }
+
+    /// Checks that `entity` is currently live and at the expected
+    /// generation, i.e. that it has not since been despawned.
+    pub fn check_generation(&self, entity: EntityId) -> Result<(), GenerationMismatch> {
+        match self.slots.get(entity.index() as usize) {
+            Some(slot) if slot.alive && slot.generation == entity.generation() => Ok(()),
+            _ => Err(GenerationMismatch),
+        }
+    }
+
+    /// Reconstructs the full `EntityId` (including generation) of the
+    /// entity currently live at `index`.
+    ///
+    /// Panics if `index` is out of bounds, and in debug builds also if
+    /// it refers to a dead entity; callers are expected to only pass
+    /// indices read back out of a component storage that is known to
+    /// be in sync with `self`.
+    pub(crate) fn id_at(&self, index: u32) -> EntityId {
+        let slot = &self.slots[index as usize];
+        debug_assert!(slot.alive, "id_at called with a dead entity index");
+        EntityId::new(index, slot.generation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reinstate_rejects_any_live_generation_mismatch() {
+        let mut entities = Entities::default();
+        let entity = entities.allocate();
+
+        // Simulate the slot having moved on to a newer generation
+        // (e.g. despawned and respawned) since `entity` was handed out.
+        entities.deallocate(entity).unwrap();
+        let current = entities.allocate();
+        assert!(current.generation() > entity.generation());
+
+        // Reinstating the live slot's own generation is a no-op...
+        assert!(entities.reinstate(current).is_ok());
+        // ...but `Entities` itself never advances or clobbers a live
+        // occupant of a *different* generation, in either direction —
+        // despawning a stale older occupant first is
+        // `Ecs::insert_or_spawn`'s job, not this method's.
+        assert!(entities.reinstate(entity).is_err());
+        assert!(entities.check_generation(current).is_ok());
+    }
+
+    #[test]
+    fn reinstate_revives_a_dead_slot_at_the_requested_generation() {
+        let mut entities = Entities::default();
+        let entity = entities.allocate();
+        entities.deallocate(entity).unwrap();
+
+        assert!(entities.reinstate(entity).is_ok());
+        assert!(entities.check_generation(entity).is_ok());
+    }
+}
@@ -0,0 +1,66 @@
+use crate::component::{Component, ComponentMeta};
+
+/// A type-erased, already-written component value produced by
+/// [`EntityBuilder::add`], ready to be moved into a `ComponentStorage`
+/// via `insert_raw`.
+pub struct BuiltComponent {
+    bytes: Box<[u8]>,
+}
+
+impl BuiltComponent {
+    fn new<T: Component>(component: T) -> Self {
+        let size = std::mem::size_of::<T>();
+        let mut bytes = vec![0u8; size].into_boxed_slice();
+        unsafe {
+            std::ptr::copy_nonoverlapping(
+                (&component as *const T).cast::<u8>(),
+                bytes.as_mut_ptr(),
+                size,
+            );
+        }
+        std::mem::forget(component);
+        Self { bytes }
+    }
+
+    /// Pointer to the start of the written component's bytes.
+    pub fn as_ptr(&self) -> *const u8 {
+        self.bytes.as_ptr()
+    }
+}
+
+/// Incrementally builds up a set of components to spawn as a single
+/// entity via [`crate::Ecs::spawn_builder`].
+///
+/// Prefer [`crate::Ecs::spawn_bundle`] with a tuple of components
+/// when the component set is known at compile time; `EntityBuilder`
+/// exists for callers (e.g. deserializing a saved entity) that add
+/// components dynamically.
+#[derive(Default)]
+pub struct EntityBuilder {
+    components: Vec<(ComponentMeta, BuiltComponent)>,
+}
+
+impl EntityBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queues `component` to be added to the next spawned entity.
+    pub fn add<T: Component>(&mut self, component: T) -> &mut Self {
+        self.components
+            .push((ComponentMeta::of::<T>(), BuiltComponent::new(component)));
+        self
+    }
+
+    /// Drains the queued components, leaving the builder empty.
+    pub(crate) fn drain(
+        &mut self,
+    ) -> impl Iterator<Item = (ComponentMeta, BuiltComponent)> + '_ {
+        self.components.drain(..)
+    }
+
+    /// Clears the builder so it can be reused for the next entity.
+    pub fn reset(&mut self) {
+        self.components.clear();
+    }
+}
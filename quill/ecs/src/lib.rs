@@ -0,0 +1,24 @@
+//! `quill-ecs` implements Quill's entity-component system: the data
+//! structure used to store game state (entities, their components,
+//! and relationships between them) that systems operate over each
+//! tick.
+
+mod bundle;
+mod change_detection;
+mod component;
+mod ecs;
+mod entity;
+mod entity_builder;
+mod mask;
+mod query;
+mod relation;
+mod storage;
+
+pub use bundle::ComponentBundle;
+pub use change_detection::{Added, Changed};
+pub use component::{Component, ComponentMeta, ComponentTypeId, StorageKind};
+pub use ecs::{ComponentError, Ecs, EntityDead, GenerationConflict};
+pub use entity::EntityId;
+pub use entity_builder::EntityBuilder;
+pub use query::{Query, QueryFilter, QueryIter};
+pub use relation::Relation;
@@ -0,0 +1,50 @@
+use crate::component::ComponentTypeId;
+
+/// Tracks which component types each entity index currently holds.
+///
+/// Stored alongside [`crate::entity::Entities`] rather than merged
+/// into it, so `Entities` stays purely about id allocation and
+/// liveness. Kept up to date on every `Ecs::insert`/`remove` so that
+/// `Ecs::despawn` only has to visit the storages an entity actually
+/// occupies, rather than every component storage in the `Ecs`.
+#[derive(Default)]
+pub(crate) struct ComponentMasks {
+    masks: Vec<Vec<ComponentTypeId>>,
+}
+
+impl ComponentMasks {
+    pub(crate) fn insert(&mut self, index: u32, type_id: ComponentTypeId) {
+        let mask = self.mask_mut(index);
+        if !mask.contains(&type_id) {
+            mask.push(type_id);
+        }
+    }
+
+    pub(crate) fn remove(&mut self, index: u32, type_id: ComponentTypeId) {
+        if let Some(mask) = self.masks.get_mut(index as usize) {
+            mask.retain(|&held| held != type_id);
+        }
+    }
+
+    /// Clears the mask for `index`, e.g. because the entity at that
+    /// index was just despawned and its index may be recycled.
+    pub(crate) fn clear(&mut self, index: u32) {
+        if let Some(mask) = self.masks.get_mut(index as usize) {
+            mask.clear();
+        }
+    }
+
+    pub(crate) fn get(&self, index: u32) -> &[ComponentTypeId] {
+        self.masks
+            .get(index as usize)
+            .map(Vec::as_slice)
+            .unwrap_or(&[])
+    }
+
+    fn mask_mut(&mut self, index: u32) -> &mut Vec<ComponentTypeId> {
+        if self.masks.len() <= index as usize {
+            self.masks.resize_with(index as usize + 1, Vec::new);
+        }
+        &mut self.masks[index as usize]
+    }
+}
@@ -0,0 +1,262 @@
+use std::marker::PhantomData;
+
+use crate::{
+    component::{Component, ComponentTypeId},
+    ecs::Ecs,
+    entity::EntityId,
+    storage::ComponentStorage,
+};
+
+/// A single fetched component access within a [`Query`] — either
+/// `&T` or `&mut T`.
+///
+/// # Safety
+/// Implementors must only ever read/write the component type
+/// reported by `storage`/`fetch` together, and `fetch` must not be
+/// called with a `dense` index that is out of bounds for `storage`.
+pub unsafe trait QueryParam<'a> {
+    type Item;
+    type Component: Component;
+
+    fn storage(ecs: &'a Ecs) -> Option<&'a ComponentStorage>;
+
+    /// # Safety
+    /// `dense` must be a valid dense index into `storage`.
+    unsafe fn fetch(storage: &'a ComponentStorage, dense: usize, tick: u64) -> Self::Item;
+}
+
+unsafe impl<'a, T: Component> QueryParam<'a> for &'a T {
+    type Item = &'a T;
+    type Component = T;
+
+    fn storage(ecs: &'a Ecs) -> Option<&'a ComponentStorage> {
+        ecs.storage_for_type::<T>()
+    }
+
+    unsafe fn fetch(storage: &'a ComponentStorage, dense: usize, _tick: u64) -> Self::Item {
+        storage.get_dense_unchecked::<T>(dense)
+    }
+}
+
+unsafe impl<'a, T: Component> QueryParam<'a> for &'a mut T {
+    type Item = &'a mut T;
+    type Component = T;
+
+    fn storage(ecs: &'a Ecs) -> Option<&'a ComponentStorage> {
+        ecs.storage_for_type::<T>()
+    }
+
+    // SAFETY: `Ecs::query` borrows the whole `Ecs` for `'a` and every
+    // dense slot is visited at most once per call to `fetch`, so
+    // handing out `&mut T` here never aliases another live borrow,
+    // as long as the caller does not request the same component type
+    // both by `&` and `&mut` in one query tuple (enforced on every
+    // query construction, see `assert_distinct_components`).
+    unsafe fn fetch(storage: &'a ComponentStorage, dense: usize, tick: u64) -> Self::Item {
+        let storage = storage as *const ComponentStorage as *mut ComponentStorage;
+        // Stamped eagerly on fetch, same as `Ecs::get_mut`: a `&mut T`
+        // handed out by a query is assumed mutated, so `Changed<T>`
+        // sees it on the next run regardless of whether the caller
+        // actually wrote through it.
+        (*storage).mark_changed_dense(dense, tick);
+        (*storage).get_dense_unchecked_mut::<T>(dense)
+    }
+}
+
+/// A tuple of [`QueryParam`]s that can be iterated over an [`Ecs`]
+/// via [`Ecs::query`].
+pub trait Query<'a> {
+    type Item;
+
+    /// The component storage with the fewest entries among the
+    /// query's params, paired with its type id so [`Self::fetch`] can
+    /// recognize it and skip re-probing it for membership.
+    #[doc(hidden)]
+    fn driving_storage(ecs: &'a Ecs) -> Option<(ComponentTypeId, &'a ComponentStorage)>;
+
+    /// # Safety
+    /// `index` must be a live entity index in `ecs`, and `driving`
+    /// must be the `(type id, dense index)` of `index` within the
+    /// storage [`Self::driving_storage`] returned for `ecs`.
+    #[doc(hidden)]
+    unsafe fn fetch(
+        ecs: &'a Ecs,
+        index: u32,
+        driving: (ComponentTypeId, usize),
+        tick: u64,
+    ) -> Option<Self::Item>;
+}
+
+/// Panics if `type_ids` contains a duplicate, i.e. a query tuple
+/// requested the same component type more than once (`&T` alongside
+/// `&mut T`, or `&mut T` twice). Nothing else stops such a query from
+/// handing out two references to the exact same storage slot, at
+/// least one of them mutable, so this is checked on every query
+/// construction rather than left to the caller to avoid.
+fn assert_distinct_components(type_ids: &[ComponentTypeId]) {
+    for i in 0..type_ids.len() {
+        for j in (i + 1)..type_ids.len() {
+            assert!(
+                type_ids[i] != type_ids[j],
+                "query requests the same component type more than once, which would alias references to it"
+            );
+        }
+    }
+}
+
+macro_rules! impl_query {
+    ($($param:ident),+) => {
+        impl<'a, $($param: QueryParam<'a>),+> Query<'a> for ($($param,)+) {
+            type Item = ($($param::Item,)+);
+
+            fn driving_storage(ecs: &'a Ecs) -> Option<(ComponentTypeId, &'a ComponentStorage)> {
+                let type_ids = [$(ComponentTypeId::of::<$param::Component>()),+];
+                assert_distinct_components(&type_ids);
+
+                [$((ComponentTypeId::of::<$param::Component>(), $param::storage(ecs))),+]
+                    .into_iter()
+                    .filter_map(|(type_id, storage)| storage.map(|storage| (type_id, storage)))
+                    .min_by_key(|(_, storage)| storage.len())
+            }
+
+            unsafe fn fetch(
+                ecs: &'a Ecs,
+                index: u32,
+                driving: (ComponentTypeId, usize),
+                tick: u64,
+            ) -> Option<Self::Item> {
+                Some(($(
+                    {
+                        let storage = $param::storage(ecs)?;
+                        // The driving storage already knows `index`'s
+                        // dense slot (it's how we got `index` in the
+                        // first place); skip the redundant probe.
+                        let dense = if ComponentTypeId::of::<$param::Component>() == driving.0 {
+                            driving.1
+                        } else {
+                            storage.dense_index_of(index)?
+                        };
+                        $param::fetch(storage, dense, tick)
+                    },
+                )+))
+            }
+        }
+    };
+}
+
+impl_query!(A);
+impl_query!(A, B);
+impl_query!(A, B, C);
+impl_query!(A, B, C, D);
+impl_query!(A, B, C, D, E);
+impl_query!(A, B, C, D, E, F);
+
+/// A predicate evaluated per-entity alongside a [`Query`], such as
+/// [`crate::change_detection::Added`] or
+/// [`crate::change_detection::Changed`].
+///
+/// Composable: a tuple of filters matches only if every element does.
+pub trait QueryFilter<'a> {
+    fn matches(&self, ecs: &'a Ecs, index: u32) -> bool;
+}
+
+impl<'a> QueryFilter<'a> for () {
+    fn matches(&self, _ecs: &'a Ecs, _index: u32) -> bool {
+        true
+    }
+}
+
+macro_rules! impl_query_filter {
+    ($($param:ident),+) => {
+        impl<'a, $($param: QueryFilter<'a>),+> QueryFilter<'a> for ($($param,)+) {
+            #[allow(non_snake_case)]
+            fn matches(&self, ecs: &'a Ecs, index: u32) -> bool {
+                let ($($param,)+) = self;
+                $($param.matches(ecs, index))&&+
+            }
+        }
+    };
+}
+
+impl_query_filter!(A);
+impl_query_filter!(A, B);
+impl_query_filter!(A, B, C);
+
+/// Iterator over every entity matching a [`Query`] (and, if present,
+/// a [`QueryFilter`]), yielded as `(EntityId, Q::Item)`.
+///
+/// Iterates the dense array of whichever requested component has the
+/// fewest entries (the "driving" storage), probing the other
+/// storages' sparse indices to confirm the entity holds every
+/// requested component. If the driving storage is a
+/// `StorageKind::Table`, fetching it back out costs nothing beyond
+/// the array read itself, since its dense index is already known.
+pub struct QueryIter<'a, Q: Query<'a>, F: QueryFilter<'a> = ()> {
+    ecs: &'a Ecs,
+    driving: Option<(ComponentTypeId, &'a [u32])>,
+    cursor: usize,
+    filter: F,
+    tick: u64,
+    _marker: PhantomData<Q>,
+}
+
+impl<'a, Q: Query<'a>> QueryIter<'a, Q, ()> {
+    pub(crate) fn new(ecs: &'a Ecs) -> Self {
+        Self::with_filter(ecs, ())
+    }
+}
+
+impl<'a, Q: Query<'a>, F: QueryFilter<'a>> QueryIter<'a, Q, F> {
+    pub(crate) fn with_filter(ecs: &'a Ecs, filter: F) -> Self {
+        let driving = Q::driving_storage(ecs).map(|(type_id, storage)| (type_id, storage.dense_entities()));
+        Self {
+            ecs,
+            driving,
+            cursor: 0,
+            filter,
+            tick: ecs.current_tick(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, Q: Query<'a>, F: QueryFilter<'a>> Iterator for QueryIter<'a, Q, F> {
+    type Item = (EntityId, Q::Item);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (driving_type, driving_entities) = self.driving?;
+
+        while self.cursor < driving_entities.len() {
+            let dense = self.cursor;
+            let index = driving_entities[dense];
+            self.cursor += 1;
+
+            if !self.filter.matches(self.ecs, index) {
+                continue;
+            }
+
+            // SAFETY: `index`/`dense` were just read from the driving
+            // storage's own dense array.
+            if let Some(item) = unsafe { Q::fetch(self.ecs, index, (driving_type, dense), self.tick) } {
+                return Some((self.ecs.entity_id_at(index), item));
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{impl_component, Ecs};
+
+    struct Position(f32);
+    impl_component!(Position);
+
+    #[test]
+    #[should_panic(expected = "same component type more than once")]
+    fn query_rejects_aliasing_the_same_component() {
+        let mut ecs = Ecs::new();
+        ecs.spawn_bundle((Position(0.0),));
+        ecs.query::<(&Position, &mut Position)>();
+    }
+}
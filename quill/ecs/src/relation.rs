@@ -0,0 +1,130 @@
+use std::any::{Any, TypeId};
+
+use ahash::AHashMap;
+
+use crate::entity::EntityId;
+
+/// Marker trait for types that can be used as relationship data, e.g.
+/// `ChildOf` or `RidingEntity`. Blanket-implemented for every
+/// `Send + Sync + 'static` type (unlike [`crate::Component`], which is
+/// implemented via `impl_component!`); there is nothing to implement
+/// by hand.
+pub trait Relation: Send + Sync + 'static {}
+
+impl<T> Relation for T where T: Send + Sync + 'static {}
+
+/// Identifies a relation by its Rust type's `TypeId`. Relation kinds
+/// are kept in their own maps, separate from component storages.
+type RelationTypeId = TypeId;
+
+struct RelationEntry {
+    target: EntityId,
+    data: Box<dyn Any + Send + Sync>,
+}
+
+/// Stores entity relationships: typed `(RelationKind, EntityId)`
+/// pairs attached to a source entity, e.g. `ChildOf(parent)`.
+///
+/// Unlike components, a source entity may hold many instances of the
+/// same relation kind, each pointing at a different target, so
+/// relations are kept in their own store rather than as a regular
+/// `ComponentStorage`.
+#[derive(Default)]
+pub(crate) struct RelationStore {
+    outgoing: AHashMap<EntityId, AHashMap<RelationTypeId, Vec<RelationEntry>>>,
+    /// Reverse index from a target entity to every `(kind, source)`
+    /// pair pointing at it, so despawning a heavily-referenced entity
+    /// doesn't require scanning every other entity's relations.
+    incoming: AHashMap<EntityId, Vec<(RelationTypeId, EntityId)>>,
+}
+
+impl RelationStore {
+    pub(crate) fn add<R: Relation>(&mut self, source: EntityId, target: EntityId, data: R) {
+        let kind = RelationTypeId::of::<R>();
+        self.outgoing
+            .entry(source)
+            .or_default()
+            .entry(kind)
+            .or_default()
+            .push(RelationEntry {
+                target,
+                data: Box::new(data),
+            });
+        self.incoming.entry(target).or_default().push((kind, source));
+    }
+
+    pub(crate) fn remove<R: Relation>(&mut self, source: EntityId, target: EntityId) -> bool {
+        let kind = RelationTypeId::of::<R>();
+        let removed = self
+            .outgoing
+            .get_mut(&source)
+            .and_then(|by_kind| by_kind.get_mut(&kind))
+            .map(|entries| {
+                let before = entries.len();
+                entries.retain(|entry| entry.target != target);
+                entries.len() != before
+            })
+            .unwrap_or(false);
+
+        if removed {
+            if let Some(targeting) = self.incoming.get_mut(&target) {
+                targeting.retain(|&(k, s)| !(k == kind && s == source));
+            }
+        }
+
+        removed
+    }
+
+    pub(crate) fn iter<R: Relation>(
+        &self,
+        source: EntityId,
+    ) -> impl Iterator<Item = (EntityId, &R)> {
+        self.outgoing
+            .get(&source)
+            .and_then(|by_kind| by_kind.get(&RelationTypeId::of::<R>()))
+            .into_iter()
+            .flatten()
+            .map(|entry| {
+                (
+                    entry.target,
+                    entry
+                        .data
+                        .downcast_ref::<R>()
+                        .expect("relation kind maps 1:1 to its Rust type"),
+                )
+            })
+    }
+
+    /// Removes every relation pair targeting `target`, wherever its
+    /// source entity is, via the reverse index rather than scanning
+    /// every source's relations.
+    pub(crate) fn remove_all_targeting(&mut self, target: EntityId) {
+        let Some(targeting) = self.incoming.remove(&target) else {
+            return;
+        };
+        for (kind, source) in targeting {
+            if let Some(entries) = self
+                .outgoing
+                .get_mut(&source)
+                .and_then(|by_kind| by_kind.get_mut(&kind))
+            {
+                entries.retain(|entry| entry.target != target);
+            }
+        }
+    }
+
+    /// Removes every relation `source` holds, e.g. because `source`
+    /// itself was despawned.
+    pub(crate) fn remove_all_from(&mut self, source: EntityId) {
+        let Some(by_kind) = self.outgoing.remove(&source) else {
+            return;
+        };
+        for (kind, entries) in by_kind {
+            for entry in entries {
+                if let Some(targeting) = self.incoming.get_mut(&entry.target) {
+                    targeting.retain(|&(k, s)| !(k == kind && s == source));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,534 @@
+use std::alloc::{self, Layout};
+use std::ptr::NonNull;
+
+use crate::component::{Component, ComponentMeta, StorageKind};
+
+/// A growable, type-erased, densely packed byte buffer. Both storage
+/// backends below use one to hold their component values contiguously;
+/// it owns the allocation and knows how to drop its own elements, so
+/// neither backend needs a manual `Drop` impl.
+struct RawColumn {
+    meta: ComponentMeta,
+    data: NonNull<u8>,
+    len: usize,
+    cap: usize,
+}
+
+unsafe impl Send for RawColumn {}
+unsafe impl Sync for RawColumn {}
+
+impl RawColumn {
+    fn new(meta: ComponentMeta) -> Self {
+        Self {
+            meta,
+            data: NonNull::dangling(),
+            len: 0,
+            cap: 0,
+        }
+    }
+
+    fn elem_size(&self) -> usize {
+        self.meta.layout.size().max(1)
+    }
+
+    unsafe fn slot(&self, at: usize) -> *mut u8 {
+        self.data.as_ptr().add(at * self.elem_size())
+    }
+
+    /// # Safety
+    /// `at < self.len`.
+    unsafe fn get<T>(&self, at: usize) -> &T {
+        &*(self.slot(at) as *const T)
+    }
+
+    /// # Safety
+    /// `at < self.len`.
+    unsafe fn get_mut<T>(&mut self, at: usize) -> &mut T {
+        &mut *(self.slot(at) as *mut T)
+    }
+
+    /// Appends one element to the end, taking ownership of `src`'s bytes.
+    ///
+    /// # Safety
+    /// `src` must point to a valid, initialized instance of this
+    /// column's component type; its bytes are moved, not copied — the
+    /// caller must not drop or reuse them afterward.
+    unsafe fn push(&mut self, src: *const u8) {
+        self.reserve(self.len + 1);
+        std::ptr::copy_nonoverlapping(src, self.slot(self.len), self.meta.layout.size());
+        self.len += 1;
+    }
+
+    /// Inserts at `at`, shifting every later element one slot to the
+    /// right. O(n) in the number of elements after `at`.
+    ///
+    /// # Safety
+    /// Same requirements as [`Self::push`]; `at <= self.len`.
+    unsafe fn insert_shifted(&mut self, at: usize, src: *const u8) {
+        self.reserve(self.len + 1);
+        if at < self.len {
+            let elem_size = self.elem_size();
+            std::ptr::copy(self.slot(at), self.slot(at + 1), (self.len - at) * elem_size);
+        }
+        std::ptr::copy_nonoverlapping(src, self.slot(at), self.meta.layout.size());
+        self.len += 1;
+    }
+
+    /// Drops and overwrites the element at `at`.
+    ///
+    /// # Safety
+    /// Same requirements as [`Self::push`]; `at < self.len`.
+    unsafe fn overwrite(&mut self, at: usize, src: *const u8) {
+        let dst = self.slot(at);
+        (self.meta.drop_in_place)(dst);
+        std::ptr::copy_nonoverlapping(src, dst, self.meta.layout.size());
+    }
+
+    /// Drops the element at `at` and moves the last element into its
+    /// place. O(1), but does not preserve order.
+    ///
+    /// # Safety
+    /// `at < self.len`.
+    unsafe fn swap_remove(&mut self, at: usize) {
+        let removed = self.slot(at);
+        (self.meta.drop_in_place)(removed);
+        let last = self.len - 1;
+        if at != last {
+            std::ptr::copy_nonoverlapping(self.slot(last), removed, self.elem_size());
+        }
+        self.len -= 1;
+    }
+
+    /// Drops the element at `at` and shifts every later element one
+    /// slot to the left. O(n), but preserves order.
+    ///
+    /// # Safety
+    /// `at < self.len`.
+    unsafe fn shift_remove(&mut self, at: usize) {
+        let removed = self.slot(at);
+        (self.meta.drop_in_place)(removed);
+        if at + 1 < self.len {
+            let elem_size = self.elem_size();
+            std::ptr::copy(self.slot(at + 1), self.slot(at), (self.len - at - 1) * elem_size);
+        }
+        self.len -= 1;
+    }
+
+    fn reserve(&mut self, min_cap: usize) {
+        if min_cap <= self.cap {
+            return;
+        }
+        let new_cap = (self.cap.max(1) * 2).max(min_cap);
+        let elem_size = self.elem_size();
+
+        let new_layout = Layout::from_size_align(new_cap * elem_size, self.meta.layout.align())
+            .expect("component storage layout overflow");
+
+        let new_data = unsafe {
+            let ptr = alloc::alloc(new_layout);
+            if ptr.is_null() {
+                alloc::handle_alloc_error(new_layout);
+            }
+            if self.cap > 0 {
+                std::ptr::copy_nonoverlapping(self.data.as_ptr(), ptr, self.len * elem_size);
+                let old_layout = Layout::from_size_align(self.cap * elem_size, self.meta.layout.align())
+                    .expect("component storage layout overflow");
+                alloc::dealloc(self.data.as_ptr(), old_layout);
+            }
+            NonNull::new_unchecked(ptr)
+        };
+
+        self.data = new_data;
+        self.cap = new_cap;
+    }
+}
+
+impl Drop for RawColumn {
+    fn drop(&mut self) {
+        let elem_size = self.elem_size();
+        for i in 0..self.len {
+            unsafe {
+                (self.meta.drop_in_place)(self.slot(i));
+            }
+        }
+        if self.cap > 0 {
+            let layout = Layout::from_size_align(self.cap * elem_size, self.meta.layout.align())
+                .expect("component storage layout overflow");
+            unsafe {
+                alloc::dealloc(self.data.as_ptr(), layout);
+            }
+        }
+    }
+}
+
+/// Sparse-set-backed storage for every entity's instance of a single
+/// component type.
+///
+/// A `sparse` array, indexed by entity index, maps to the owning slot
+/// in the dense, type-erased column and its parallel
+/// `dense_entities` array. Gives O(1) insert/get/remove via
+/// swap-remove, at the cost of the sparse array's indirection versus
+/// [`TableStorage`].
+pub struct SparseSetStorage {
+    sparse: Vec<Option<u32>>,
+    dense_entities: Vec<u32>,
+    added_ticks: Vec<u64>,
+    changed_ticks: Vec<u64>,
+    column: RawColumn,
+}
+
+impl SparseSetStorage {
+    fn new(meta: ComponentMeta) -> Self {
+        Self {
+            sparse: Vec::new(),
+            dense_entities: Vec::new(),
+            added_ticks: Vec::new(),
+            changed_ticks: Vec::new(),
+            column: RawColumn::new(meta),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.column.len
+    }
+
+    fn dense_entities(&self) -> &[u32] {
+        &self.dense_entities
+    }
+
+    fn dense_index_of(&self, index: u32) -> Option<usize> {
+        self.sparse
+            .get(index as usize)
+            .copied()
+            .flatten()
+            .map(|dense| dense as usize)
+    }
+
+    fn added_tick(&self, dense: usize) -> u64 {
+        self.added_ticks[dense]
+    }
+
+    fn changed_tick(&self, dense: usize) -> u64 {
+        self.changed_ticks[dense]
+    }
+
+    fn mark_changed(&mut self, index: u32, tick: u64) {
+        if let Some(dense) = self.dense_index_of(index) {
+            self.changed_ticks[dense] = tick;
+        }
+    }
+
+    fn mark_changed_dense(&mut self, dense: usize, tick: u64) {
+        self.changed_ticks[dense] = tick;
+    }
+
+    unsafe fn insert_raw(&mut self, index: u32, src: *const u8, tick: u64) {
+        if let Some(dense) = self.dense_index_of(index) {
+            self.column.overwrite(dense, src);
+            self.added_ticks[dense] = tick;
+            self.changed_ticks[dense] = tick;
+            return;
+        }
+
+        let dense = self.column.len;
+        self.column.push(src);
+        self.dense_entities.push(index);
+        self.added_ticks.push(tick);
+        self.changed_ticks.push(tick);
+
+        if self.sparse.len() <= index as usize {
+            self.sparse.resize(index as usize + 1, None);
+        }
+        self.sparse[index as usize] = Some(dense as u32);
+    }
+
+    fn reserve_additional(&mut self, additional: usize) {
+        self.column.reserve(self.column.len + additional);
+    }
+
+    fn remove(&mut self, index: u32) -> bool {
+        let Some(dense) = self.dense_index_of(index) else {
+            return false;
+        };
+
+        unsafe {
+            self.column.swap_remove(dense);
+        }
+
+        let last = self.dense_entities.len() - 1;
+        if dense != last {
+            let moved_entity = self.dense_entities[last];
+            self.dense_entities[dense] = moved_entity;
+            self.added_ticks[dense] = self.added_ticks[last];
+            self.changed_ticks[dense] = self.changed_ticks[last];
+            self.sparse[moved_entity as usize] = Some(dense as u32);
+        }
+
+        self.dense_entities.pop();
+        self.added_ticks.pop();
+        self.changed_ticks.pop();
+        self.sparse[index as usize] = None;
+        true
+    }
+}
+
+/// Dense, table-like storage for every entity's instance of a single
+/// component type.
+///
+/// Unlike [`SparseSetStorage`], there is no sparse index — entries
+/// are kept sorted by entity index in `dense_entities` and probed via
+/// binary search. Structural changes are O(n), in exchange for a
+/// query driven by this storage walking it with no indirection.
+pub struct TableStorage {
+    dense_entities: Vec<u32>,
+    added_ticks: Vec<u64>,
+    changed_ticks: Vec<u64>,
+    column: RawColumn,
+}
+
+impl TableStorage {
+    fn new(meta: ComponentMeta) -> Self {
+        Self {
+            dense_entities: Vec::new(),
+            added_ticks: Vec::new(),
+            changed_ticks: Vec::new(),
+            column: RawColumn::new(meta),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.column.len
+    }
+
+    fn dense_entities(&self) -> &[u32] {
+        &self.dense_entities
+    }
+
+    fn dense_index_of(&self, index: u32) -> Option<usize> {
+        self.dense_entities.binary_search(&index).ok()
+    }
+
+    fn added_tick(&self, dense: usize) -> u64 {
+        self.added_ticks[dense]
+    }
+
+    fn changed_tick(&self, dense: usize) -> u64 {
+        self.changed_ticks[dense]
+    }
+
+    fn mark_changed(&mut self, index: u32, tick: u64) {
+        if let Some(dense) = self.dense_index_of(index) {
+            self.changed_ticks[dense] = tick;
+        }
+    }
+
+    fn mark_changed_dense(&mut self, dense: usize, tick: u64) {
+        self.changed_ticks[dense] = tick;
+    }
+
+    unsafe fn insert_raw(&mut self, index: u32, src: *const u8, tick: u64) {
+        match self.dense_entities.binary_search(&index) {
+            Ok(dense) => {
+                self.column.overwrite(dense, src);
+                self.added_ticks[dense] = tick;
+                self.changed_ticks[dense] = tick;
+            }
+            Err(at) => {
+                self.column.insert_shifted(at, src);
+                self.dense_entities.insert(at, index);
+                self.added_ticks.insert(at, tick);
+                self.changed_ticks.insert(at, tick);
+            }
+        }
+    }
+
+    fn reserve_additional(&mut self, additional: usize) {
+        self.column.reserve(self.column.len + additional);
+    }
+
+    fn remove(&mut self, index: u32) -> bool {
+        let Ok(dense) = self.dense_entities.binary_search(&index) else {
+            return false;
+        };
+
+        unsafe {
+            self.column.shift_remove(dense);
+        }
+        self.dense_entities.remove(dense);
+        self.added_ticks.remove(dense);
+        self.changed_ticks.remove(dense);
+        true
+    }
+}
+
+/// A single component type's storage, backed by whichever
+/// [`StorageKind`] the component picked via [`Component::STORAGE`].
+pub enum ComponentStorage {
+    Sparse(SparseSetStorage),
+    Table(TableStorage),
+}
+
+impl ComponentStorage {
+    pub fn new(meta: ComponentMeta) -> Self {
+        match meta.storage_kind {
+            StorageKind::SparseSet => ComponentStorage::Sparse(SparseSetStorage::new(meta)),
+            StorageKind::Table => ComponentStorage::Table(TableStorage::new(meta)),
+        }
+    }
+
+    /// Number of entities currently holding this component.
+    pub fn len(&self) -> usize {
+        match self {
+            Self::Sparse(s) => s.len(),
+            Self::Table(t) => t.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The packed list of entity indices that currently hold this
+    /// component. Used by the query iterator to drive iteration over
+    /// the smallest involved storage.
+    pub fn dense_entities(&self) -> &[u32] {
+        match self {
+            Self::Sparse(s) => s.dense_entities(),
+            Self::Table(t) => t.dense_entities(),
+        }
+    }
+
+    /// Looks up the dense slot an entity's component lives in,
+    /// without needing to know the component's Rust type.
+    pub fn dense_index_of(&self, index: u32) -> Option<usize> {
+        match self {
+            Self::Sparse(s) => s.dense_index_of(index),
+            Self::Table(t) => t.dense_index_of(index),
+        }
+    }
+
+    fn column(&self) -> &RawColumn {
+        match self {
+            Self::Sparse(s) => &s.column,
+            Self::Table(t) => &t.column,
+        }
+    }
+
+    fn column_mut(&mut self) -> &mut RawColumn {
+        match self {
+            Self::Sparse(s) => &mut s.column,
+            Self::Table(t) => &mut t.column,
+        }
+    }
+
+    /// Gets a typed reference to the component at the given dense slot.
+    ///
+    /// # Safety
+    /// `dense_index` must be `< self.len()` and `T` must be the
+    /// component type this storage was created for.
+    pub unsafe fn get_dense_unchecked<T: Component>(&self, dense_index: usize) -> &T {
+        self.column().get(dense_index)
+    }
+
+    /// Gets a typed mutable reference to the component at the given
+    /// dense slot.
+    ///
+    /// # Safety
+    /// Same requirements as [`Self::get_dense_unchecked`].
+    pub unsafe fn get_dense_unchecked_mut<T: Component>(&mut self, dense_index: usize) -> &mut T {
+        self.column_mut().get_mut(dense_index)
+    }
+
+    pub fn get<T: Component>(&self, index: u32) -> Option<&T> {
+        let dense = self.dense_index_of(index)?;
+        Some(unsafe { self.get_dense_unchecked(dense) })
+    }
+
+    pub fn get_mut<T: Component>(&mut self, index: u32) -> Option<&mut T> {
+        let dense = self.dense_index_of(index)?;
+        Some(unsafe { self.get_dense_unchecked_mut(dense) })
+    }
+
+    /// The tick this dense slot's component was last inserted at.
+    pub fn added_tick(&self, dense: usize) -> u64 {
+        match self {
+            Self::Sparse(s) => s.added_tick(dense),
+            Self::Table(t) => t.added_tick(dense),
+        }
+    }
+
+    /// The tick this dense slot's component was last mutated at. A
+    /// freshly inserted component is also "changed" as of that tick,
+    /// since `insert`/`insert_raw` set both arrays together.
+    pub fn changed_tick(&self, dense: usize) -> u64 {
+        match self {
+            Self::Sparse(s) => s.changed_tick(dense),
+            Self::Table(t) => t.changed_tick(dense),
+        }
+    }
+
+    /// Records that the component for `index` was mutated at `tick`,
+    /// if it exists. No-op if `index` does not hold this component.
+    pub fn mark_changed(&mut self, index: u32, tick: u64) {
+        match self {
+            Self::Sparse(s) => s.mark_changed(index, tick),
+            Self::Table(t) => t.mark_changed(index, tick),
+        }
+    }
+
+    /// Like [`Self::mark_changed`], but for a caller that already
+    /// knows the dense slot (e.g. the query iterator), avoiding a
+    /// redundant `dense_index_of` lookup.
+    pub fn mark_changed_dense(&mut self, dense: usize, tick: u64) {
+        match self {
+            Self::Sparse(s) => s.mark_changed_dense(dense, tick),
+            Self::Table(t) => t.mark_changed_dense(dense, tick),
+        }
+    }
+
+    /// Inserts (or overwrites) the component for `index`, stamping it
+    /// with `tick` as both its added and changed tick.
+    pub fn insert<T: Component>(&mut self, index: u32, component: T, tick: u64) {
+        let mut component = component;
+        unsafe {
+            self.insert_raw(index, (&mut component as *mut T).cast(), tick);
+        }
+        std::mem::forget(component);
+    }
+
+    /// Inserts the component for `index` from a type-erased pointer,
+    /// stamping the slot's added/changed ticks with `tick`.
+    ///
+    /// # Safety
+    /// `src` must point to a valid, initialized instance of the
+    /// component type this storage was created for; ownership of
+    /// those bytes moves into the storage (the caller must not drop
+    /// or reuse them afterward).
+    pub unsafe fn insert_raw(&mut self, index: u32, src: *const u8, tick: u64) {
+        match self {
+            Self::Sparse(s) => s.insert_raw(index, src, tick),
+            Self::Table(t) => t.insert_raw(index, src, tick),
+        }
+    }
+
+    /// Reserves capacity for `additional` more components without
+    /// reallocating, on top of however many are already stored.
+    ///
+    /// Used by batch-spawning to grow a storage once up front rather
+    /// than once per inserted entity.
+    pub fn reserve_additional(&mut self, additional: usize) {
+        match self {
+            Self::Sparse(s) => s.reserve_additional(additional),
+            Self::Table(t) => t.reserve_additional(additional),
+        }
+    }
+
+    /// Removes the component for `index`, if present, dropping it.
+    /// Returns whether a component was removed.
+    pub fn remove(&mut self, index: u32) -> bool {
+        match self {
+            Self::Sparse(s) => s.remove(index),
+            Self::Table(t) => t.remove(index),
+        }
+    }
+}